@@ -1,7 +1,7 @@
-use std::{fs, time::Instant};
+use std::time::Instant;
 
 use log::info;
-use radixdb::{DynBlobStore, PagedFileStore, TreeNode};
+use radixdb::{DynBlobStore, PagedFileStore, TreeNode, CODEC_STORED};
 use tempfile::tempdir;
 
 fn do_test(mut store: DynBlobStore) -> anyhow::Result<()> {
@@ -79,16 +79,11 @@ fn browser_compare() -> anyhow::Result<()> {
     init_logger();
     let dir = tempdir()?;
     let path = dir.path().join("large2.rdb");
-    let file = fs::OpenOptions::new()
-        .create(true)
-        .read(true)
-        .write(true)
-        .open(&path)?;
-    let db = PagedFileStore::<1048576>::new(file).unwrap();
+    let db = PagedFileStore::<1048576, CODEC_STORED>::open(&path, true).unwrap();
     let store: DynBlobStore = Box::new(db);
     do_test(store)
 }
 
 fn main() {
     browser_compare().unwrap()
-}
\ No newline at end of file
+}